@@ -0,0 +1,551 @@
+use std::collections::HashMap;
+
+use ash::{vk, Device, Instance};
+
+/// Size of each `VkDeviceMemory` block carved out per memory type. Resources
+/// are sub-allocated from these rather than getting one allocation each,
+/// since drivers cap `maxMemoryAllocationCount`.
+const BLOCK_SIZE: vk::DeviceSize = 256 * 1024 * 1024;
+
+/// A sub-allocation handed back to a caller. `offset` is relative to
+/// `memory` and already respects both the requested alignment and
+/// `bufferImageGranularity`.
+#[derive(Clone, Copy)]
+pub struct Allocation {
+    pub memory: vk::DeviceMemory,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+    memory_type_index: u32,
+    block_index: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ChunkState {
+    Free,
+    Used { is_linear: bool },
+}
+
+#[derive(Clone, Copy)]
+struct Chunk {
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    state: ChunkState,
+}
+
+struct Block {
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    // Address-ordered, used to find a chunk's neighbours for
+    // `bufferImageGranularity` padding and coalescing.
+    chunks: Vec<Chunk>,
+    // Free chunk offsets bucketed by `size.ilog2()`, so a fitting chunk can
+    // be found by walking buckets instead of scanning every chunk in the
+    // block. A chunk of size `size` can always be found via the bucket
+    // `size.ilog2()`, and any chunk at least as big as a power-of-two
+    // request lives in a bucket >= the request's own bucket.
+    free_lists: HashMap<u32, Vec<vk::DeviceSize>>,
+}
+
+impl Block {
+    fn bucket_of(size: vk::DeviceSize) -> u32 {
+        size.max(1).ilog2()
+    }
+
+    fn add_free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        self.free_lists
+            .entry(Self::bucket_of(size))
+            .or_default()
+            .push(offset);
+    }
+
+    fn remove_free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        if let Some(offsets) = self.free_lists.get_mut(&Self::bucket_of(size)) {
+            if let Some(position) = offsets.iter().position(|&o| o == offset) {
+                offsets.swap_remove(position);
+            }
+        }
+    }
+
+    fn chunk_index_at(&self, offset: vk::DeviceSize) -> Option<usize> {
+        self.chunks.binary_search_by_key(&offset, |c| c.offset).ok()
+    }
+}
+
+/// A segregated free-list sub-allocator over `VkDeviceMemory`. Each memory
+/// type gets its own set of blocks (`BLOCK_SIZE`, or larger if a single
+/// request doesn't fit); within a block, free chunks are indexed by
+/// power-of-two size-class buckets so an allocation can find a fit without
+/// scanning every chunk, splitting the chosen chunk if it's larger than
+/// needed and coalescing neighbours back together on free.
+pub struct Allocator {
+    buffer_image_granularity: vk::DeviceSize,
+    // `None` marks a slot whose block has been freed back to the driver;
+    // keeping the slot (rather than removing it) means a `block_index`
+    // handed out in an `Allocation` stays valid for the lifetime of the
+    // allocator.
+    blocks: HashMap<u32, Vec<Option<Block>>>,
+}
+
+impl Allocator {
+    pub fn new(instance: &Instance, physical_device: vk::PhysicalDevice) -> Self {
+        let limits = unsafe { instance.get_physical_device_properties(physical_device) }.limits;
+
+        Self {
+            buffer_image_granularity: limits.buffer_image_granularity,
+            blocks: HashMap::new(),
+        }
+    }
+
+    /// Maps a resource's `memory_type_bits` (from `VkMemoryRequirements`)
+    /// plus the desired property flags to a concrete memory type index.
+    pub fn find_memory_type(
+        instance: &Instance,
+        physical_device: vk::PhysicalDevice,
+        memory_type_bits: u32,
+        desired_properties: vk::MemoryPropertyFlags,
+    ) -> Option<u32> {
+        let memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(physical_device) };
+
+        (0..memory_properties.memory_type_count).find(|&index| {
+            let type_supported = memory_type_bits & (1 << index) != 0;
+            let properties_supported = memory_properties.memory_types[index as usize]
+                .property_flags
+                .contains(desired_properties);
+
+            type_supported && properties_supported
+        })
+    }
+
+    pub fn allocate(
+        &mut self,
+        device: &Device,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        memory_type_index: u32,
+        is_linear: bool,
+    ) -> Result<Allocation, vk::Result> {
+        let size_class = size.max(1).next_power_of_two();
+        let blocks = self.blocks.entry(memory_type_index).or_default();
+
+        for (block_index, slot) in blocks.iter_mut().enumerate() {
+            let Some(block) = slot else { continue };
+
+            if let Some(offset) = Self::place_in_block(
+                block,
+                size_class,
+                alignment,
+                is_linear,
+                self.buffer_image_granularity,
+            ) {
+                return Ok(Allocation {
+                    memory: block.memory,
+                    offset,
+                    size: size_class,
+                    memory_type_index,
+                    block_index,
+                });
+            }
+        }
+
+        // A request larger than a whole block (e.g. a big texture) still
+        // gets a dedicated block, just sized to fit it instead of the
+        // usual BLOCK_SIZE.
+        let block_size = size_class.max(BLOCK_SIZE);
+        let mut block = Self::allocate_block(device, memory_type_index, block_size)?;
+        let offset = Self::place_in_block(
+            &mut block,
+            size_class,
+            alignment,
+            is_linear,
+            self.buffer_image_granularity,
+        )
+        .expect("a freshly allocated block must fit the requested size class");
+
+        let block_index = match blocks.iter().position(|slot| slot.is_none()) {
+            Some(empty_slot) => {
+                blocks[empty_slot] = Some(block);
+                empty_slot
+            }
+            None => {
+                blocks.push(Some(block));
+                blocks.len() - 1
+            }
+        };
+
+        Ok(Allocation {
+            memory: blocks[block_index].as_ref().unwrap().memory,
+            offset,
+            size: size_class,
+            memory_type_index,
+            block_index,
+        })
+    }
+
+    /// Returns a chunk to its block, coalescing it with free neighbours.
+    /// Once a block coalesces down to a single fully-free chunk, its
+    /// `VkDeviceMemory` is freed back to the driver immediately rather than
+    /// being kept around for reuse.
+    pub fn free(&mut self, device: &Device, allocation: Allocation) {
+        let Some(blocks) = self.blocks.get_mut(&allocation.memory_type_index) else {
+            return;
+        };
+        let Some(Some(block)) = blocks.get_mut(allocation.block_index) else {
+            return;
+        };
+
+        let Some(index) = block.chunk_index_at(allocation.offset) else {
+            return;
+        };
+        if block.chunks[index].state == ChunkState::Free {
+            return;
+        }
+
+        block.chunks[index].state = ChunkState::Free;
+        block.add_free(block.chunks[index].offset, block.chunks[index].size);
+        Self::coalesce(block, index);
+
+        if block.chunks.len() == 1 && block.chunks[0].state == ChunkState::Free {
+            let memory = block.memory;
+            blocks[allocation.block_index] = None;
+            unsafe { device.free_memory(memory, None) };
+        }
+    }
+
+    /// Frees every remaining `VkDeviceMemory` block back to the driver.
+    /// Must be called before the owning `VkDevice` is destroyed.
+    pub fn destroy(&mut self, device: &Device) {
+        for blocks in self.blocks.values_mut() {
+            for slot in blocks.iter_mut() {
+                if let Some(block) = slot.take() {
+                    unsafe { device.free_memory(block.memory, None) };
+                }
+            }
+        }
+    }
+
+    fn allocate_block(
+        device: &Device,
+        memory_type_index: u32,
+        size: vk::DeviceSize,
+    ) -> Result<Block, vk::Result> {
+        let allocate_info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(size)
+            .memory_type_index(memory_type_index)
+            .build();
+
+        let memory = unsafe { device.allocate_memory(&allocate_info, None)? };
+
+        let mut block = Block {
+            memory,
+            size,
+            chunks: vec![Chunk {
+                offset: 0,
+                size,
+                state: ChunkState::Free,
+            }],
+            free_lists: HashMap::new(),
+        };
+        block.add_free(0, size);
+
+        Ok(block)
+    }
+
+    /// Finds a free chunk able to hold `size` once `alignment` and
+    /// `bufferImageGranularity` are accounted for, by walking size-class
+    /// buckets from `size`'s own bucket upward, splits it, and returns the
+    /// allocation's offset.
+    fn place_in_block(
+        block: &mut Block,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        is_linear: bool,
+        granularity: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize> {
+        let start_bucket = Block::bucket_of(size);
+        let max_bucket = Block::bucket_of(block.size);
+
+        for bucket in start_bucket..=max_bucket {
+            let Some(offsets) = block.free_lists.get(&bucket) else {
+                continue;
+            };
+            // Cloned so placement attempts below (which mutate `block`)
+            // don't hold a borrow of `free_lists` across the loop.
+            let candidates = offsets.clone();
+
+            for offset in candidates {
+                let Some(index) = block.chunk_index_at(offset) else {
+                    continue;
+                };
+                if block.chunks[index].state != ChunkState::Free {
+                    continue;
+                }
+
+                let Some(start) =
+                    Self::fit_in_chunk(block, index, size, alignment, is_linear, granularity)
+                else {
+                    continue;
+                };
+
+                let chunk_offset = block.chunks[index].offset;
+                let chunk_size = block.chunks[index].size;
+                block.remove_free(chunk_offset, chunk_size);
+
+                Self::split_chunk(block, index, start, start + size, is_linear);
+                return Some(start);
+            }
+        }
+
+        None
+    }
+
+    /// Checks whether `size` fits in the free chunk at `index`, accounting
+    /// for `alignment` and `bufferImageGranularity` padding against used
+    /// neighbours, returning the (possibly padded) start offset if so.
+    fn fit_in_chunk(
+        block: &Block,
+        index: usize,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        is_linear: bool,
+        granularity: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize> {
+        let chunk_offset = block.chunks[index].offset;
+        let chunk_end = chunk_offset + block.chunks[index].size;
+
+        let mut start = align_up(chunk_offset, alignment);
+
+        // A linear and a non-linear resource must not share a granularity
+        // page, or the driver may alias and corrupt them.
+        if index > 0 {
+            if let ChunkState::Used { is_linear: prev_linear } = block.chunks[index - 1].state {
+                if prev_linear != is_linear {
+                    let prev_end = block.chunks[index - 1].offset + block.chunks[index - 1].size;
+                    start = align_up(start.max(align_up(prev_end, granularity)), alignment);
+                }
+            }
+        }
+
+        let end = start + size;
+        if end > chunk_end {
+            return None;
+        }
+
+        if index + 1 < block.chunks.len() {
+            if let ChunkState::Used { is_linear: next_linear } = block.chunks[index + 1].state {
+                if next_linear != is_linear {
+                    let next_page_start = align_down(block.chunks[index + 1].offset, granularity);
+                    if end > next_page_start {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        Some(start)
+    }
+
+    fn split_chunk(
+        block: &mut Block,
+        index: usize,
+        start: vk::DeviceSize,
+        end: vk::DeviceSize,
+        is_linear: bool,
+    ) {
+        let chunk_offset = block.chunks[index].offset;
+        let chunk_end = chunk_offset + block.chunks[index].size;
+
+        let mut replacement = Vec::with_capacity(3);
+
+        if start > chunk_offset {
+            replacement.push(Chunk {
+                offset: chunk_offset,
+                size: start - chunk_offset,
+                state: ChunkState::Free,
+            });
+        }
+
+        replacement.push(Chunk {
+            offset: start,
+            size: end - start,
+            state: ChunkState::Used { is_linear },
+        });
+
+        if end < chunk_end {
+            replacement.push(Chunk {
+                offset: end,
+                size: chunk_end - end,
+                state: ChunkState::Free,
+            });
+        }
+
+        block.chunks.splice(index..=index, replacement);
+
+        if start > chunk_offset {
+            block.add_free(chunk_offset, start - chunk_offset);
+        }
+        if end < chunk_end {
+            block.add_free(end, chunk_end - end);
+        }
+    }
+
+    fn coalesce(block: &mut Block, index: usize) {
+        let mut index = index;
+
+        if index + 1 < block.chunks.len() && block.chunks[index + 1].state == ChunkState::Free {
+            let cur_offset = block.chunks[index].offset;
+            let cur_size = block.chunks[index].size;
+            let next_offset = block.chunks[index + 1].offset;
+            let next_size = block.chunks[index + 1].size;
+
+            block.remove_free(cur_offset, cur_size);
+            block.remove_free(next_offset, next_size);
+
+            block.chunks[index].size += next_size;
+            block.chunks.remove(index + 1);
+            block.add_free(block.chunks[index].offset, block.chunks[index].size);
+        }
+
+        if index > 0 && block.chunks[index - 1].state == ChunkState::Free {
+            let cur_offset = block.chunks[index].offset;
+            let cur_size = block.chunks[index].size;
+            let prev_offset = block.chunks[index - 1].offset;
+            let prev_size = block.chunks[index - 1].size;
+
+            block.remove_free(cur_offset, cur_size);
+            block.remove_free(prev_offset, prev_size);
+
+            block.chunks[index - 1].size += cur_size;
+            block.chunks.remove(index);
+            index -= 1;
+            block.add_free(block.chunks[index].offset, block.chunks[index].size);
+        }
+    }
+}
+
+fn align_up(value: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+    if alignment == 0 {
+        return value;
+    }
+    value.div_ceil(alignment) * alignment
+}
+
+fn align_down(value: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+    if alignment == 0 {
+        return value;
+    }
+    value / alignment * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_block(size: vk::DeviceSize) -> Block {
+        let mut block = Block {
+            memory: vk::DeviceMemory::null(),
+            size,
+            chunks: vec![Chunk {
+                offset: 0,
+                size,
+                state: ChunkState::Free,
+            }],
+            free_lists: HashMap::new(),
+        };
+        block.add_free(0, size);
+        block
+    }
+
+    #[test]
+    fn place_in_block_splits_and_leaves_a_free_remainder() {
+        let mut block = empty_block(4096);
+
+        let offset = Allocator::place_in_block(&mut block, 256, 1, true, 0).unwrap();
+
+        assert_eq!(offset, 0);
+        assert_eq!(block.chunks.len(), 2);
+        assert_eq!(block.chunks[0].state, ChunkState::Used { is_linear: true });
+        assert_eq!(block.chunks[0].size, 256);
+        assert_eq!(block.chunks[1].state, ChunkState::Free);
+        assert_eq!(block.chunks[1].offset, 256);
+        assert_eq!(block.chunks[1].size, 3840);
+    }
+
+    #[test]
+    fn place_in_block_fails_once_the_block_is_full() {
+        let mut block = empty_block(256);
+
+        assert!(Allocator::place_in_block(&mut block, 256, 1, true, 0).is_some());
+        assert!(Allocator::place_in_block(&mut block, 1, 1, true, 0).is_none());
+    }
+
+    #[test]
+    fn coalesce_merges_freed_chunk_with_both_neighbours() {
+        let mut block = empty_block(4096);
+
+        // Carve out three adjacent used chunks, then free the middle one
+        // followed by its neighbours, exercising both the "merge with
+        // next" and "merge with previous" coalesce paths.
+        Allocator::place_in_block(&mut block, 1024, 1, true, 0).unwrap();
+        Allocator::place_in_block(&mut block, 1024, 1, true, 0).unwrap();
+        Allocator::place_in_block(&mut block, 1024, 1, true, 0).unwrap();
+        assert_eq!(block.chunks.len(), 4);
+
+        let middle = block.chunk_index_at(1024).unwrap();
+        block.chunks[middle].state = ChunkState::Free;
+        block.add_free(1024, 1024);
+        Allocator::coalesce(&mut block, middle);
+        assert_eq!(block.chunks.len(), 4);
+
+        let first = block.chunk_index_at(0).unwrap();
+        block.chunks[first].state = ChunkState::Free;
+        block.add_free(0, 1024);
+        Allocator::coalesce(&mut block, first);
+        assert_eq!(block.chunks.len(), 3);
+        assert_eq!(block.chunks[0].state, ChunkState::Free);
+        assert_eq!(block.chunks[0].size, 2048);
+
+        let last = block.chunk_index_at(2048).unwrap();
+        block.chunks[last].state = ChunkState::Free;
+        block.add_free(2048, 1024);
+        Allocator::coalesce(&mut block, last);
+        assert_eq!(block.chunks.len(), 1);
+        assert_eq!(block.chunks[0].state, ChunkState::Free);
+        assert_eq!(block.chunks[0].size, 4096);
+    }
+
+    #[test]
+    fn buffer_image_granularity_separates_linear_and_non_linear_neighbours() {
+        let mut block = empty_block(4096);
+        let granularity = 256;
+
+        let linear_offset =
+            Allocator::place_in_block(&mut block, 64, 1, true, granularity).unwrap();
+        assert_eq!(linear_offset, 0);
+
+        // A non-linear allocation placed right after must be pushed to the
+        // next granularity page rather than sitting at offset 64, or the
+        // two resources could alias on the driver's page granularity.
+        let non_linear_offset =
+            Allocator::place_in_block(&mut block, 64, 1, false, granularity).unwrap();
+        assert_eq!(non_linear_offset, granularity);
+    }
+
+    #[test]
+    fn align_up_rounds_to_the_next_multiple() {
+        assert_eq!(align_up(0, 256), 0);
+        assert_eq!(align_up(1, 256), 256);
+        assert_eq!(align_up(256, 256), 256);
+        assert_eq!(align_up(257, 256), 512);
+        assert_eq!(align_up(123, 0), 123);
+    }
+
+    #[test]
+    fn align_down_rounds_to_the_previous_multiple() {
+        assert_eq!(align_down(0, 256), 0);
+        assert_eq!(align_down(255, 256), 0);
+        assert_eq!(align_down(256, 256), 256);
+        assert_eq!(align_down(511, 256), 256);
+        assert_eq!(align_down(123, 0), 123);
+    }
+}
@@ -1,4 +1,7 @@
-use std::ffi::{c_void, CStr, CString};
+use std::{
+    env,
+    ffi::{c_void, CStr, CString},
+};
 
 use ash::{
     extensions::ext::DebugUtils,
@@ -13,6 +16,64 @@ use ash::{
 const VALIDATION_LAYERS: [&str; 1] = ["VK_LAYER_KHRONOS_validation"];
 
 const SHOULD_INCLUDE_VALIDATION_LAYERS: bool = cfg!(debug_assertions);
+
+/// Message IDs that are known to be noisy but not actionable, keyed on
+/// `DebugUtilsMessengerCallbackDataEXT::message_id_number`. These are
+/// skipped regardless of the configured severity.
+const SUPPRESSED_MESSAGE_IDS: &[i32] = &[
+    -602894578, // UNASSIGNED-BestPractices-vkCreateInstance-specialuse-extension
+    0x675dc32e_u32 as i32, // loader notice about layer override ordering
+];
+
+/// Controls which `DebugUtilsMessageSeverityFlagsEXT` bits the debug
+/// messenger reports, read from the `OXIDE_VK_DEBUG` environment variable
+/// as a comma-separated list of `error`, `warning`, `info`, `verbose`.
+/// Defaults to `error,warning,info` when unset or unparsable.
+#[derive(Clone, Copy)]
+pub struct DebugConfig {
+    severity: DebugUtilsMessageSeverityFlagsEXT,
+}
+
+impl DebugConfig {
+    const ENV_VAR: &'static str = "OXIDE_VK_DEBUG";
+
+    fn from_env() -> Self {
+        let Ok(raw) = env::var(Self::ENV_VAR) else {
+            return Self::default();
+        };
+
+        let mut severity = DebugUtilsMessageSeverityFlagsEXT::empty();
+        for level in raw.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+            severity |= match level {
+                "error" => DebugUtilsMessageSeverityFlagsEXT::ERROR,
+                "warning" => DebugUtilsMessageSeverityFlagsEXT::WARNING,
+                "info" => DebugUtilsMessageSeverityFlagsEXT::INFO,
+                "verbose" => DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+                other => {
+                    log::warn!("Unknown {} level '{other}', ignoring it", Self::ENV_VAR);
+                    DebugUtilsMessageSeverityFlagsEXT::empty()
+                }
+            };
+        }
+
+        if severity.is_empty() {
+            return Self::default();
+        }
+
+        Self { severity }
+    }
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            severity: DebugUtilsMessageSeverityFlagsEXT::INFO
+                | DebugUtilsMessageSeverityFlagsEXT::WARNING
+                | DebugUtilsMessageSeverityFlagsEXT::ERROR,
+        }
+    }
+}
+
 pub struct VulkanDebugger {
     debug_utils_messenger: DebugUtilsMessengerEXT,
 }
@@ -20,27 +81,52 @@ pub struct VulkanDebugger {
 impl VulkanDebugger {
     unsafe extern "system" fn vulkan_debug_callback(
         message_severity: DebugUtilsMessageSeverityFlagsEXT,
-        _message_types: DebugUtilsMessageTypeFlagsEXT,
+        message_types: DebugUtilsMessageTypeFlagsEXT,
         p_callback_data: *const DebugUtilsMessengerCallbackDataEXT,
         _p_data: *mut c_void,
     ) -> vk::Bool32 {
-        let message_pointer = (*p_callback_data).p_message;
-        let message = CStr::from_ptr(message_pointer);
+        // Avoid re-entrant logging if we're already unwinding (e.g. a panic
+        // during teardown while validation is still tearing down objects).
+        if std::thread::panicking() {
+            return vk::FALSE;
+        }
+
+        let callback_data = &*p_callback_data;
+
+        if SUPPRESSED_MESSAGE_IDS.contains(&callback_data.message_id_number) {
+            return vk::FALSE;
+        }
+
+        let message = CStr::from_ptr(callback_data.p_message);
+        let message_type = Self::message_type_label(message_types);
 
         if message_severity.contains(DebugUtilsMessageSeverityFlagsEXT::ERROR) {
-            log::error!("{}", message.to_str().unwrap());
+            log::error!("[{message_type}] {}", message.to_str().unwrap());
         } else if message_severity.contains(DebugUtilsMessageSeverityFlagsEXT::WARNING) {
-            log::warn!("{}", message.to_str().unwrap());
+            log::warn!("[{message_type}] {}", message.to_str().unwrap());
         } else if message_severity.contains(DebugUtilsMessageSeverityFlagsEXT::INFO) {
-            log::info!("{}", message.to_str().unwrap());
+            log::info!("[{message_type}] {}", message.to_str().unwrap());
         } else if message_severity.contains(DebugUtilsMessageSeverityFlagsEXT::VERBOSE) {
-            log::debug!("{}", message.to_str().unwrap());
+            log::debug!("[{message_type}] {}", message.to_str().unwrap());
         }
         vk::FALSE
     }
 
+    fn message_type_label(message_types: DebugUtilsMessageTypeFlagsEXT) -> &'static str {
+        if message_types.contains(DebugUtilsMessageTypeFlagsEXT::VALIDATION) {
+            "VALIDATION"
+        } else if message_types.contains(DebugUtilsMessageTypeFlagsEXT::PERFORMANCE) {
+            "PERFORMANCE"
+        } else {
+            "GENERAL"
+        }
+    }
+
     pub fn new(entry: &Entry, instance: &Instance) -> Option<Result<Self, vk::Result>> {
-        if !SHOULD_INCLUDE_VALIDATION_LAYERS {
+        let debug_utils_available =
+            SHOULD_INCLUDE_VALIDATION_LAYERS && Self::extension_is_available(entry, DebugUtils::name());
+
+        if !debug_utils_available {
             return None;
         }
 
@@ -54,43 +140,77 @@ impl VulkanDebugger {
         }))
     }
 
-    pub fn add_necessary_extensions(extension_list: &mut Vec<&CStr>) {
-        if SHOULD_INCLUDE_VALIDATION_LAYERS {
+    pub fn add_necessary_extensions(entry: &Entry, extension_list: &mut Vec<&CStr>) {
+        let debug_utils_available =
+            SHOULD_INCLUDE_VALIDATION_LAYERS && Self::extension_is_available(entry, DebugUtils::name());
+
+        if debug_utils_available {
             extension_list.push(DebugUtils::name());
         }
     }
 
-    pub fn add_necessary_layers(layer_list: &mut Vec<CString>) {
-        if SHOULD_INCLUDE_VALIDATION_LAYERS {
-            layer_list.extend(
-                VALIDATION_LAYERS
-                    .iter()
-                    .filter_map(|string| CString::new(*string).ok()),
-            );
+    pub fn add_necessary_layers(entry: &Entry, layer_list: &mut Vec<CString>) {
+        if !SHOULD_INCLUDE_VALIDATION_LAYERS {
+            return;
+        }
+
+        for name in VALIDATION_LAYERS {
+            let Ok(layer_name) = CString::new(name) else {
+                continue;
+            };
+
+            if Self::layer_is_available(entry, &layer_name) {
+                layer_list.push(layer_name);
+            } else {
+                log::warn!("Validation layer '{name}' is not available, skipping it");
+            }
         }
     }
 
-    pub fn get_debug_messenger_info() -> Option<DebugUtilsMessengerCreateInfoEXT> {
-        SHOULD_INCLUDE_VALIDATION_LAYERS.then(Self::create_debug_messenger_create_info)
+    pub fn get_debug_messenger_info(entry: &Entry) -> Option<DebugUtilsMessengerCreateInfoEXT> {
+        let debug_utils_available =
+            SHOULD_INCLUDE_VALIDATION_LAYERS && Self::extension_is_available(entry, DebugUtils::name());
+
+        debug_utils_available
+            .then(|| Self::create_debug_messenger_create_info(DebugConfig::from_env()))
+    }
+
+    fn layer_is_available(entry: &Entry, name: &CStr) -> bool {
+        let Ok(available_layers) = (unsafe { entry.enumerate_instance_layer_properties() }) else {
+            return false;
+        };
+
+        available_layers
+            .iter()
+            .any(|layer| unsafe { CStr::from_ptr(layer.layer_name.as_ptr()) } == name)
+    }
+
+    fn extension_is_available(entry: &Entry, name: &CStr) -> bool {
+        let Ok(available_extensions) =
+            (unsafe { entry.enumerate_instance_extension_properties(None) })
+        else {
+            return false;
+        };
+
+        available_extensions
+            .iter()
+            .any(|ext| unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) } == name)
     }
 
     fn init_debug_messenger(
         entry: &Entry,
         instance: &Instance,
     ) -> Result<DebugUtilsMessengerEXT, vk::Result> {
-        let debug_messenger_info = Self::create_debug_messenger_create_info();
+        let debug_messenger_info =
+            Self::create_debug_messenger_create_info(DebugConfig::from_env());
 
         let debug_utils_loader = DebugUtils::new(entry, instance);
         unsafe { debug_utils_loader.create_debug_utils_messenger(&debug_messenger_info, None) }
     }
 
-    fn create_debug_messenger_create_info() -> DebugUtilsMessengerCreateInfoEXT {
+    fn create_debug_messenger_create_info(config: DebugConfig) -> DebugUtilsMessengerCreateInfoEXT {
         DebugUtilsMessengerCreateInfoEXT::builder()
-            .message_severity(
-                DebugUtilsMessageSeverityFlagsEXT::INFO
-                    | DebugUtilsMessageSeverityFlagsEXT::WARNING
-                    | DebugUtilsMessageSeverityFlagsEXT::ERROR,
-            )
+            .message_severity(config.severity)
             .message_type(
                 DebugUtilsMessageTypeFlagsEXT::GENERAL
                     | DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
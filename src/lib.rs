@@ -1,11 +1,19 @@
+pub mod allocator;
 mod debug;
+mod surface;
 
 use crate::debug::VulkanDebugger;
+use crate::surface::Swapchain;
 
-use std::{error::Error, ffi::CString};
+use std::{
+    collections::HashSet,
+    error::Error,
+    ffi::{CStr, CString},
+};
 
 use ash::{
-    vk::{self, PhysicalDeviceType, QueueFamilyProperties},
+    extensions::khr::Surface,
+    vk::{self, PhysicalDeviceType},
     Entry, Instance,
 };
 use winit::{
@@ -19,14 +27,27 @@ use winit::{
 const WIDTH: f64 = 800.0;
 const HEIGHT: f64 = 600.0;
 
+#[derive(Clone, Copy)]
+struct QueueFamilyIndices {
+    graphics_family: u32,
+    present_family: u32,
+}
+
 pub struct HelloTriangleApplication {
     entry: Entry,
     event_loop: EventLoop<()>,
     instance: Instance,
     debugger: Option<VulkanDebugger>,
+    physical_device: vk::PhysicalDevice,
     device: ash::Device,
-    _queue: vk::Queue,
-    _window: Window,
+    _graphics_queue: vk::Queue,
+    _present_queue: vk::Queue,
+    graphics_family: u32,
+    present_family: u32,
+    surface_loader: Surface,
+    surface: vk::SurfaceKHR,
+    swapchain: Swapchain,
+    window: Window,
 }
 
 impl HelloTriangleApplication {
@@ -41,48 +62,79 @@ impl HelloTriangleApplication {
             None => None,
         };
 
-        let physical_device = Self::pick_physical_device(&instance)
-            .ok_or("Unable to find suitable physical device")?;
+        let (surface_loader, surface) = surface::create_surface(&entry, &instance, &window)?;
 
-        let device = Self::create_logical_device(&instance, physical_device)?;
+        let (physical_device, indices) =
+            Self::pick_physical_device(&instance, &surface_loader, surface)
+                .ok_or("Unable to find suitable physical device")?;
 
-        let (index, _) = Self::find_queue_families(&instance, &physical_device).unwrap();
+        let device = Self::create_logical_device(&entry, &instance, physical_device, &indices)?;
 
-        let queue = unsafe { device.get_device_queue(index as u32, 0) };
+        let graphics_queue = unsafe { device.get_device_queue(indices.graphics_family, 0) };
+        let present_queue = unsafe { device.get_device_queue(indices.present_family, 0) };
+
+        let swapchain = Swapchain::new(
+            &instance,
+            &device,
+            physical_device,
+            &surface_loader,
+            surface,
+            &window,
+            indices.graphics_family,
+            indices.present_family,
+        )?;
 
         Ok(Self {
             entry,
             event_loop,
             instance,
             debugger,
+            physical_device,
             device,
-            _queue: queue,
-            _window: window,
+            _graphics_queue: graphics_queue,
+            _present_queue: present_queue,
+            graphics_family: indices.graphics_family,
+            present_family: indices.present_family,
+            surface_loader,
+            surface,
+            swapchain,
+            window,
         })
     }
 
     fn create_logical_device(
+        entry: &Entry,
         instance: &Instance,
         device: vk::PhysicalDevice,
+        indices: &QueueFamilyIndices,
     ) -> Result<ash::Device, vk::Result> {
-        let (index, _) = Self::find_queue_families(instance, &device).unwrap();
-
         let mut layer_names = vec![];
-        VulkanDebugger::add_necessary_layers(&mut layer_names);
+        VulkanDebugger::add_necessary_layers(entry, &mut layer_names);
 
         let layer_names_raw = layer_names
             .iter()
             .map(|ext| ext.as_ptr())
             .collect::<Vec<_>>();
 
-        let queue_device_create_info = vk::DeviceQueueCreateInfo::builder()
-            .queue_family_index(index as u32)
-            .queue_priorities(&[1.0])
-            .build();
+        let extension_names_raw = [ash::extensions::khr::Swapchain::name().as_ptr()];
+
+        let unique_families: HashSet<u32> =
+            HashSet::from([indices.graphics_family, indices.present_family]);
+
+        let queue_create_infos = unique_families
+            .into_iter()
+            .map(|family| {
+                vk::DeviceQueueCreateInfo::builder()
+                    .queue_family_index(family)
+                    .queue_priorities(&[1.0])
+                    .build()
+            })
+            .collect::<Vec<_>>();
 
         let device_create_info = vk::DeviceCreateInfo::builder()
-            .queue_create_infos(&[queue_device_create_info])
+            .queue_create_infos(&queue_create_infos)
             .enabled_layer_names(&layer_names_raw)
+            .enabled_extension_names(&extension_names_raw)
             .build();
 
         unsafe { instance.create_device(device, &device_create_info, None) }
@@ -91,29 +143,101 @@ impl HelloTriangleApplication {
     fn find_queue_families(
         instance: &Instance,
         device: &vk::PhysicalDevice,
-    ) -> Option<(usize, QueueFamilyProperties)> {
+        surface_loader: &Surface,
+        surface: vk::SurfaceKHR,
+    ) -> Option<QueueFamilyIndices> {
         let queue_families =
             unsafe { instance.get_physical_device_queue_family_properties(*device) };
 
-        queue_families
-            .into_iter()
-            .enumerate()
-            .find(|(_, qf)| qf.queue_flags.contains(vk::QueueFlags::GRAPHICS))
+        let graphics_family = queue_families
+            .iter()
+            .position(|qf| qf.queue_flags.contains(vk::QueueFlags::GRAPHICS))?;
+
+        let family_supports_present = |index: u32| -> bool {
+            unsafe {
+                surface_loader.get_physical_device_surface_support(*device, index, surface)
+            }
+            .unwrap_or(false)
+        };
+
+        // Prefer reusing the graphics family for presentation too, so the
+        // swapchain can stick to `SharingMode::EXCLUSIVE` instead of
+        // needlessly forcing `CONCURRENT` across two distinct queues.
+        let present_family = if family_supports_present(graphics_family as u32) {
+            graphics_family
+        } else {
+            (0..queue_families.len()).find(|&index| family_supports_present(index as u32))?
+        };
+
+        Some(QueueFamilyIndices {
+            graphics_family: graphics_family as u32,
+            present_family: present_family as u32,
+        })
+    }
+
+    fn device_extension_is_available(
+        instance: &Instance,
+        device: vk::PhysicalDevice,
+        name: &CStr,
+    ) -> bool {
+        let Ok(available_extensions) =
+            (unsafe { instance.enumerate_device_extension_properties(device) })
+        else {
+            return false;
+        };
+
+        available_extensions
+            .iter()
+            .any(|ext| unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) } == name)
     }
 
-    fn is_physical_device_suitable(instance: &Instance, device: &vk::PhysicalDevice) -> bool {
-        let device_properties = unsafe { instance.get_physical_device_properties(*device) };
-        // let device_features = unsafe { instance.get_physical_device_features(*device) };
+    /// Scores a physical device's suitability, favouring discrete GPUs but
+    /// falling back to integrated ones rather than refusing to run at all.
+    /// Returns `None` if the device lacks a suitable queue family or the
+    /// `VK_KHR_swapchain` extension.
+    fn score_physical_device(
+        instance: &Instance,
+        device: vk::PhysicalDevice,
+        surface_loader: &Surface,
+        surface: vk::SurfaceKHR,
+    ) -> Option<(u32, QueueFamilyIndices)> {
+        let indices = Self::find_queue_families(instance, &device, surface_loader, surface)?;
+
+        if !Self::device_extension_is_available(
+            instance,
+            device,
+            ash::extensions::khr::Swapchain::name(),
+        ) {
+            return None;
+        }
+
+        let properties = unsafe { instance.get_physical_device_properties(device) };
+
+        let mut score = match properties.device_type {
+            PhysicalDeviceType::DISCRETE_GPU => 1000,
+            PhysicalDeviceType::INTEGRATED_GPU => 100,
+            _ => 0,
+        };
+        score += properties.limits.max_image_dimension2_d;
 
-        device_properties.device_type == PhysicalDeviceType::DISCRETE_GPU
+        Some((score, indices))
     }
 
-    fn pick_physical_device(instance: &Instance) -> Option<vk::PhysicalDevice> {
+    fn pick_physical_device(
+        instance: &Instance,
+        surface_loader: &Surface,
+        surface: vk::SurfaceKHR,
+    ) -> Option<(vk::PhysicalDevice, QueueFamilyIndices)> {
         let physical_devices = unsafe { instance.enumerate_physical_devices() }.unwrap();
-        physical_devices.into_iter().find(|d| {
-            Self::is_physical_device_suitable(instance, d)
-                && Self::find_queue_families(instance, d).is_some()
-        })
+
+        physical_devices
+            .into_iter()
+            .filter_map(|device| {
+                Self::score_physical_device(instance, device, surface_loader, surface)
+                    .map(|(score, indices)| (score, device, indices))
+            })
+            .max_by_key(|(score, _, _)| *score)
+            .map(|(_, device, indices)| (device, indices))
     }
 
     fn init_vulkan(entry: &Entry, window: &Window) -> Result<Instance, vk::Result> {
@@ -127,7 +251,7 @@ impl HelloTriangleApplication {
 
         let mut surface_extensions = ash_window::enumerate_required_extensions(&window).unwrap();
 
-        VulkanDebugger::add_necessary_extensions(&mut surface_extensions);
+        VulkanDebugger::add_necessary_extensions(entry, &mut surface_extensions);
 
         let extension_names_raw = surface_extensions
             .iter()
@@ -135,7 +259,7 @@ impl HelloTriangleApplication {
             .collect::<Vec<_>>();
 
         let mut layer_names = vec![];
-        VulkanDebugger::add_necessary_layers(&mut layer_names);
+        VulkanDebugger::add_necessary_layers(entry, &mut layer_names);
 
         let layer_names_raw = layer_names
             .iter()
@@ -147,7 +271,7 @@ impl HelloTriangleApplication {
             .enabled_extension_names(&extension_names_raw)
             .enabled_layer_names(&layer_names_raw);
 
-        let debug_create_info = VulkanDebugger::get_debug_messenger_info();
+        let debug_create_info = VulkanDebugger::get_debug_messenger_info(entry);
 
         let instance_create_info = match debug_create_info {
             None => instance_create_info_builder.build(),
@@ -172,15 +296,47 @@ impl HelloTriangleApplication {
     }
 
     pub fn run(&mut self) {
+        let device = &self.device;
+        let physical_device = self.physical_device;
+        let surface_loader = &self.surface_loader;
+        let surface = self.surface;
+        let window = &self.window;
+        let graphics_family = self.graphics_family;
+        let present_family = self.present_family;
+        let swapchain = &mut self.swapchain;
+
         self.event_loop.run_return(move |event, _, control_flow| {
             *control_flow = ControlFlow::Wait;
 
-            if let Event::WindowEvent {
-                event: WindowEvent::CloseRequested,
-                ..
-            } = event
-            {
-                *control_flow = ControlFlow::Exit
+            match event {
+                Event::WindowEvent {
+                    event: WindowEvent::CloseRequested,
+                    ..
+                } => *control_flow = ControlFlow::Exit,
+                Event::WindowEvent {
+                    event: WindowEvent::Resized(size),
+                    ..
+                } => {
+                    // Minimizing the window drives the framebuffer size to
+                    // 0x0 on most platforms; recreating against a zero
+                    // extent is invalid, so just wait for the next resize
+                    // (e.g. when the window is restored) instead of
+                    // panicking.
+                    if size.width > 0 && size.height > 0 {
+                        swapchain
+                            .recreate(
+                                device,
+                                physical_device,
+                                surface_loader,
+                                surface,
+                                window,
+                                graphics_family,
+                                present_family,
+                            )
+                            .expect("Failed to recreate swapchain");
+                    }
+                }
+                _ => (),
             };
         });
     }
@@ -188,10 +344,12 @@ impl HelloTriangleApplication {
 
 impl Drop for HelloTriangleApplication {
     fn drop(&mut self) {
+        self.swapchain.destroy(&self.device);
         if let Some(debugger) = &self.debugger {
             debugger.clean_up(&self.entry, &self.instance)
         }
         unsafe { self.device.destroy_device(None) }
+        unsafe { self.surface_loader.destroy_surface(self.surface, None) }
         unsafe { self.instance.destroy_instance(None) }
     }
 }
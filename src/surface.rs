@@ -0,0 +1,291 @@
+use ash::{
+    extensions::khr::{Surface, Swapchain as SwapchainLoader},
+    vk, Device, Entry, Instance,
+};
+use winit::window::Window;
+
+pub const DEFAULT_SURFACE_FORMAT: vk::SurfaceFormatKHR = vk::SurfaceFormatKHR {
+    format: vk::Format::B8G8R8A8_SRGB,
+    color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+};
+
+pub const DEFAULT_PRESENT_MODE: vk::PresentModeKHR = vk::PresentModeKHR::FIFO;
+
+/// Creates the platform surface (and its loader) for `window`.
+pub fn create_surface(
+    entry: &Entry,
+    instance: &Instance,
+    window: &Window,
+) -> Result<(Surface, vk::SurfaceKHR), vk::Result> {
+    let surface_loader = Surface::new(entry, instance);
+    let surface = unsafe { ash_window::create_surface(entry, instance, window, None)? };
+
+    Ok((surface_loader, surface))
+}
+
+struct SwapchainSupportDetails {
+    capabilities: vk::SurfaceCapabilitiesKHR,
+    formats: Vec<vk::SurfaceFormatKHR>,
+    present_modes: Vec<vk::PresentModeKHR>,
+}
+
+impl SwapchainSupportDetails {
+    fn query(
+        surface_loader: &Surface,
+        physical_device: vk::PhysicalDevice,
+        surface: vk::SurfaceKHR,
+    ) -> Result<Self, vk::Result> {
+        unsafe {
+            Ok(Self {
+                capabilities: surface_loader
+                    .get_physical_device_surface_capabilities(physical_device, surface)?,
+                formats: surface_loader
+                    .get_physical_device_surface_formats(physical_device, surface)?,
+                present_modes: surface_loader
+                    .get_physical_device_surface_present_modes(physical_device, surface)?,
+            })
+        }
+    }
+
+    fn choose_format(&self) -> vk::SurfaceFormatKHR {
+        self.formats
+            .iter()
+            .find(|f| {
+                f.format == DEFAULT_SURFACE_FORMAT.format
+                    && f.color_space == DEFAULT_SURFACE_FORMAT.color_space
+            })
+            .copied()
+            .unwrap_or(self.formats[0])
+    }
+
+    fn choose_present_mode(&self) -> vk::PresentModeKHR {
+        self.present_modes
+            .iter()
+            .copied()
+            .find(|&mode| mode == DEFAULT_PRESENT_MODE)
+            .unwrap_or(vk::PresentModeKHR::FIFO)
+    }
+
+    fn choose_extent(&self, window: &Window) -> vk::Extent2D {
+        if self.capabilities.current_extent.width != u32::MAX {
+            return self.capabilities.current_extent;
+        }
+
+        let size = window.inner_size();
+        vk::Extent2D {
+            width: size.width.clamp(
+                self.capabilities.min_image_extent.width,
+                self.capabilities.max_image_extent.width,
+            ),
+            height: size.height.clamp(
+                self.capabilities.min_image_extent.height,
+                self.capabilities.max_image_extent.height,
+            ),
+        }
+    }
+}
+
+/// Owns the `VkSwapchainKHR` and the image views presenting to it. It does not
+/// watch the surface itself; `recreate` rebuilds it when the caller asks (e.g.
+/// on window resize).
+pub struct Swapchain {
+    loader: SwapchainLoader,
+    handle: vk::SwapchainKHR,
+    image_views: Vec<vk::ImageView>,
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+}
+
+impl Swapchain {
+    pub fn new(
+        instance: &Instance,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        surface_loader: &Surface,
+        surface: vk::SurfaceKHR,
+        window: &Window,
+        graphics_family: u32,
+        present_family: u32,
+    ) -> Result<Self, vk::Result> {
+        let loader = SwapchainLoader::new(instance, device);
+        let (handle, format, extent, image_views) = Self::create_swapchain_and_views(
+            &loader,
+            device,
+            physical_device,
+            surface_loader,
+            surface,
+            window,
+            graphics_family,
+            present_family,
+            vk::SwapchainKHR::null(),
+        )?;
+
+        Ok(Self {
+            loader,
+            handle,
+            image_views,
+            format,
+            extent,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_swapchain_and_views(
+        loader: &SwapchainLoader,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        surface_loader: &Surface,
+        surface: vk::SurfaceKHR,
+        window: &Window,
+        graphics_family: u32,
+        present_family: u32,
+        old_swapchain: vk::SwapchainKHR,
+    ) -> Result<(vk::SwapchainKHR, vk::Format, vk::Extent2D, Vec<vk::ImageView>), vk::Result> {
+        let support = SwapchainSupportDetails::query(surface_loader, physical_device, surface)?;
+        let surface_format = support.choose_format();
+        let present_mode = support.choose_present_mode();
+        let extent = support.choose_extent(window);
+
+        let mut image_count = support.capabilities.min_image_count + 1;
+        if support.capabilities.max_image_count > 0 {
+            image_count = image_count.min(support.capabilities.max_image_count);
+        }
+
+        let queue_family_indices = [graphics_family, present_family];
+        let (sharing_mode, indices): (vk::SharingMode, &[u32]) =
+            if graphics_family != present_family {
+                (vk::SharingMode::CONCURRENT, &queue_family_indices)
+            } else {
+                (vk::SharingMode::EXCLUSIVE, &[])
+            };
+
+        let create_info = vk::SwapchainCreateInfoKHR::builder()
+            .surface(surface)
+            .min_image_count(image_count)
+            .image_format(surface_format.format)
+            .image_color_space(surface_format.color_space)
+            .image_extent(extent)
+            .image_array_layers(1)
+            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_sharing_mode(sharing_mode)
+            .queue_family_indices(indices)
+            .pre_transform(support.capabilities.current_transform)
+            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+            .present_mode(present_mode)
+            .clipped(true)
+            .old_swapchain(old_swapchain)
+            .build();
+
+        let handle = unsafe { loader.create_swapchain(&create_info, None)? };
+
+        let images = match unsafe { loader.get_swapchain_images(handle) } {
+            Ok(images) => images,
+            Err(e) => {
+                unsafe { loader.destroy_swapchain(handle, None) };
+                return Err(e);
+            }
+        };
+
+        let image_views = match Self::create_image_views(device, &images, surface_format.format) {
+            Ok(views) => views,
+            Err(e) => {
+                unsafe { loader.destroy_swapchain(handle, None) };
+                return Err(e);
+            }
+        };
+
+        Ok((handle, surface_format.format, extent, image_views))
+    }
+
+    /// Creates a view per image, tearing down any views already created in
+    /// this call if a later one fails so nothing is leaked on error.
+    fn create_image_views(
+        device: &Device,
+        images: &[vk::Image],
+        format: vk::Format,
+    ) -> Result<Vec<vk::ImageView>, vk::Result> {
+        let mut image_views = Vec::with_capacity(images.len());
+
+        for &image in images {
+            let create_info = vk::ImageViewCreateInfo::builder()
+                .image(image)
+                .view_type(vk::ImageViewType::TYPE_2D)
+                .format(format)
+                .components(vk::ComponentMapping::default())
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                })
+                .build();
+
+            match unsafe { device.create_image_view(&create_info, None) } {
+                Ok(view) => image_views.push(view),
+                Err(e) => {
+                    for view in image_views {
+                        unsafe { device.destroy_image_view(view, None) };
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(image_views)
+    }
+
+    /// Recreates the swapchain and its image views against the surface's
+    /// (possibly changed) capabilities. The new swapchain is built before
+    /// the old one is torn down — passing the old handle as `oldSwapchain`
+    /// as the spec intends — so a failure here leaves the existing,
+    /// already-live swapchain untouched instead of destroying it and then
+    /// failing to replace it.
+    pub fn recreate(
+        &mut self,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        surface_loader: &Surface,
+        surface: vk::SurfaceKHR,
+        window: &Window,
+        graphics_family: u32,
+        present_family: u32,
+    ) -> Result<(), vk::Result> {
+        unsafe { device.device_wait_idle()? };
+
+        let (handle, format, extent, image_views) = Self::create_swapchain_and_views(
+            &self.loader,
+            device,
+            physical_device,
+            surface_loader,
+            surface,
+            window,
+            graphics_family,
+            present_family,
+            self.handle,
+        )?;
+
+        self.destroy_views_and_swapchain(device);
+
+        self.handle = handle;
+        self.format = format;
+        self.extent = extent;
+        self.image_views = image_views;
+
+        Ok(())
+    }
+
+    fn destroy_views_and_swapchain(&mut self, device: &Device) {
+        unsafe {
+            for &view in &self.image_views {
+                device.destroy_image_view(view, None);
+            }
+            self.loader.destroy_swapchain(self.handle, None);
+        }
+        self.image_views.clear();
+    }
+
+    pub fn destroy(&mut self, device: &Device) {
+        self.destroy_views_and_swapchain(device);
+    }
+}